@@ -0,0 +1,120 @@
+//! Axis-aligned box boundary with reflective collision response.
+
+use cgmath::Point3;
+
+use crate::{Scalar, Simulation};
+
+/// An axis-aligned box that contains the fluid.
+pub struct Domain {
+    pub origin: Point3<Scalar>,
+    pub size: cgmath::Vector3<Scalar>,
+}
+
+impl Domain {
+    fn min(&self) -> Point3<Scalar> {
+        self.origin
+    }
+
+    fn max(&self) -> Point3<Scalar> {
+        self.origin + self.size
+    }
+}
+
+/// Clamps any particle that has crossed a wall back inside the domain and
+/// reflects the velocity component along that wall's normal, damped by
+/// `restitution`.
+pub fn resolve_collisions(s: &mut Simulation, domain: &Domain, restitution: Scalar) {
+    let min = domain.min();
+    let max = domain.max();
+
+    for i in 0..s.positions.len() {
+        let pos = &mut s.positions[i];
+        let vel = &mut s.velocities[i];
+
+        if pos.x < min.x {
+            pos.x = min.x;
+            vel.x *= -restitution;
+        } else if pos.x > max.x {
+            pos.x = max.x;
+            vel.x *= -restitution;
+        }
+
+        if pos.y < min.y {
+            pos.y = min.y;
+            vel.y *= -restitution;
+        } else if pos.y > max.y {
+            pos.y = max.y;
+            vel.y *= -restitution;
+        }
+
+        if pos.z < min.z {
+            pos.z = min.z;
+            vel.z *= -restitution;
+        } else if pos.z > max.z {
+            pos.z = max.z;
+            vel.z *= -restitution;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{point3, vec3};
+
+    fn domain() -> Domain {
+        Domain {
+            origin: point3(-1., -1., -1.),
+            size: vec3(2., 2., 2.),
+        }
+    }
+
+    fn simulation(position: Point3<Scalar>, velocity: cgmath::Vector3<Scalar>) -> Simulation {
+        Simulation {
+            masses: vec![1.],
+            positions: vec![position],
+            velocities: vec![velocity],
+            force: vec![cgmath::Vector3::new(0., 0., 0.)],
+            density: vec![0.],
+            pressure: vec![0.],
+            affine_velocity: vec![cgmath::Matrix3::new(0., 0., 0., 0., 0., 0., 0., 0., 0.)],
+        }
+    }
+
+    #[test]
+    fn clamps_and_reflects_past_the_min_corner() {
+        let domain = domain();
+        let restitution = 0.5;
+        let mut s = simulation(point3(-1.5, -1.5, -1.5), vec3(-2., -3., -4.));
+
+        resolve_collisions(&mut s, &domain, restitution);
+
+        assert_eq!(s.positions[0], point3(-1., -1., -1.));
+        assert_eq!(s.velocities[0], vec3(1., 1.5, 2.));
+    }
+
+    #[test]
+    fn clamps_and_reflects_past_the_max_corner() {
+        let domain = domain();
+        let restitution = 0.5;
+        let mut s = simulation(point3(1.5, 1.5, 1.5), vec3(2., 3., 4.));
+
+        resolve_collisions(&mut s, &domain, restitution);
+
+        assert_eq!(s.positions[0], point3(1., 1., 1.));
+        assert_eq!(s.velocities[0], vec3(-1., -1.5, -2.));
+    }
+
+    #[test]
+    fn leaves_particles_inside_the_domain_untouched() {
+        let domain = domain();
+        let pos = point3(0.2, -0.3, 0.1);
+        let vel = vec3(1., 2., 3.);
+        let mut s = simulation(pos, vel);
+
+        resolve_collisions(&mut s, &domain, 0.5);
+
+        assert_eq!(s.positions[0], pos);
+        assert_eq!(s.velocities[0], vel);
+    }
+}
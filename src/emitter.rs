@@ -0,0 +1,201 @@
+//! Particle emitters: ways to seed initial particle positions.
+
+use cgmath::prelude::*;
+use cgmath::{point3, Point3};
+use rand::Rng;
+
+use crate::Scalar;
+
+/// A source of initial particle positions.
+pub enum Emitter {
+    /// Particles sampled uniformly throughout a sphere's volume.
+    Sphere {
+        center: Point3<Scalar>,
+        radius: Scalar,
+        count: usize,
+    },
+    /// Particles sampled uniformly throughout an axis-aligned box.
+    Box {
+        min: Point3<Scalar>,
+        max: Point3<Scalar>,
+        count: usize,
+    },
+    /// Particles placed on a regular grid spaced at `spacing` (typically a
+    /// fraction of `h`), which is the standard way to initialize SPH
+    /// particles near rest density.
+    Lattice {
+        origin: Point3<Scalar>,
+        dims: (usize, usize, usize),
+        spacing: Scalar,
+    },
+}
+
+/// Samples a standard normal variate via the Box-Muller transform.
+fn gaussian(rng: &mut impl Rng) -> Scalar {
+    let u1: Scalar = rng.gen::<Scalar>().max(Scalar::EPSILON);
+    let u2: Scalar = rng.gen();
+    (-2. * u1.ln()).sqrt() * (2. * std::f32::consts::PI * u2).cos()
+}
+
+impl Emitter {
+    /// Generates the positions for this emitter.
+    pub fn seed(&self, rng: &mut impl Rng) -> Vec<Point3<Scalar>> {
+        match self {
+            Emitter::Sphere {
+                center,
+                radius,
+                count,
+            } => (0..*count)
+                .map(|_| {
+                    // r = R * u^(1/3) and a Gaussian-normalized direction give a
+                    // uniform distribution over the sphere's volume, unlike naive
+                    // rejection sampling or a uniform radius (which biases toward
+                    // the center / surface respectively).
+                    let u: Scalar = rng.gen();
+                    let r = radius * u.cbrt();
+                    let dir = cgmath::vec3(gaussian(rng), gaussian(rng), gaussian(rng)).normalize();
+                    center + r * dir
+                })
+                .collect(),
+            Emitter::Box { min, max, count } => (0..*count)
+                .map(|_| {
+                    point3(
+                        min.x + rng.gen::<Scalar>() * (max.x - min.x),
+                        min.y + rng.gen::<Scalar>() * (max.y - min.y),
+                        min.z + rng.gen::<Scalar>() * (max.z - min.z),
+                    )
+                })
+                .collect(),
+            Emitter::Lattice {
+                origin,
+                dims,
+                spacing,
+            } => {
+                let (nx, ny, nz) = *dims;
+                let mut positions = Vec::with_capacity(nx * ny * nz);
+                for i in 0..nx {
+                    for j in 0..ny {
+                        for k in 0..nz {
+                            positions.push(
+                                origin + cgmath::vec3(i as Scalar, j as Scalar, k as Scalar) * *spacing,
+                            );
+                        }
+                    }
+                }
+                positions
+            }
+        }
+    }
+
+    /// The number of particles this emitter will produce.
+    pub fn count(&self) -> usize {
+        match self {
+            Emitter::Sphere { count, .. } => *count,
+            Emitter::Box { count, .. } => *count,
+            Emitter::Lattice { dims, .. } => dims.0 * dims.1 * dims.2,
+        }
+    }
+
+    /// Picks an emitter from an optional `SPH_EMITTER`-style value
+    /// (`"sphere"`, `"box"`, or `"lattice"`, case-insensitive), defaulting to
+    /// a unit sphere of 1000 particles if unset or unrecognized.
+    fn from_value(v: Option<&str>) -> Self {
+        match v {
+            Some(v) if v.eq_ignore_ascii_case("box") => Emitter::Box {
+                min: point3(-1., -1., -1.),
+                max: point3(1., 1., 1.),
+                count: 1000,
+            },
+            Some(v) if v.eq_ignore_ascii_case("lattice") => Emitter::Lattice {
+                origin: point3(-1., -1., -1.),
+                dims: (10, 10, 10),
+                spacing: 0.2,
+            },
+            _ => Emitter::Sphere {
+                center: point3(0., 0., 0.),
+                radius: 1.,
+                count: 1000,
+            },
+        }
+    }
+
+    /// Picks an emitter from the `SPH_EMITTER` environment variable. See
+    /// [`Self::from_value`].
+    pub fn from_env() -> Self {
+        Self::from_value(std::env::var("SPH_EMITTER").ok().as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn box_emitter_stays_within_bounds() {
+        let min = point3(-1., -2., -3.);
+        let max = point3(1., 2., 3.);
+        let emitter = Emitter::Box { min, max, count: 200 };
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let positions = emitter.seed(&mut rng);
+        assert_eq!(positions.len(), emitter.count());
+        for p in positions {
+            assert!(p.x >= min.x && p.x <= max.x);
+            assert!(p.y >= min.y && p.y <= max.y);
+            assert!(p.z >= min.z && p.z <= max.z);
+        }
+    }
+
+    #[test]
+    fn lattice_emitter_produces_regularly_spaced_grid() {
+        let origin = point3(0., 0., 0.);
+        let spacing = 0.5;
+        let emitter = Emitter::Lattice {
+            origin,
+            dims: (2, 3, 4),
+            spacing,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let positions = emitter.seed(&mut rng);
+        assert_eq!(positions.len(), 2 * 3 * 4);
+        assert_eq!(positions.len(), emitter.count());
+
+        for p in &positions {
+            for &c in &[p.x, p.y, p.z] {
+                let steps = c / spacing;
+                assert!((steps - steps.round()).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_emitter_samples_stay_within_radius() {
+        let center = point3(1., -1., 2.);
+        let radius = 3.;
+        let emitter = Emitter::Sphere {
+            center,
+            radius,
+            count: 200,
+        };
+        let mut rng = StdRng::seed_from_u64(11);
+
+        for p in emitter.seed(&mut rng) {
+            assert!((p - center).magnitude() <= radius + 1e-4);
+        }
+    }
+
+    #[test]
+    fn from_value_defaults_to_sphere() {
+        assert!(matches!(Emitter::from_value(None), Emitter::Sphere { .. }));
+        assert!(matches!(Emitter::from_value(Some("bogus")), Emitter::Sphere { .. }));
+    }
+
+    #[test]
+    fn from_value_picks_box_and_lattice() {
+        assert!(matches!(Emitter::from_value(Some("box")), Emitter::Box { .. }));
+        assert!(matches!(Emitter::from_value(Some("lattice")), Emitter::Lattice { .. }));
+    }
+}
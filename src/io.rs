@@ -0,0 +1,221 @@
+//! Binary frame dump/replay format for running the solver headless and
+//! inspecting specific timesteps offline, decoupled from the live renderer.
+//!
+//! Each frame is laid out as:
+//! - `u32` particle count (little-endian)
+//! - `u8` flags (bit 0: velocities follow, bit 1: densities follow)
+//! - `count` packed `[f32; 3]` positions
+//! - if flagged, `count` packed `[f32; 3]` velocities
+//! - if flagged, `count` packed `f32` densities
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use cgmath::{point3, vec3};
+
+use crate::{Scalar, Simulation, Vec3};
+
+const VELOCITIES_FLAG: u8 = 1 << 0;
+const DENSITIES_FLAG: u8 = 1 << 1;
+
+/// A single decoded frame, as returned by [`FrameReader`].
+pub struct Frame {
+    pub positions: Vec<cgmath::Point3<Scalar>>,
+    pub velocities: Option<Vec<Vec3>>,
+    pub densities: Option<Vec<Scalar>>,
+}
+
+/// Appends simulation frames to a binary dump file.
+pub struct FrameWriter {
+    file: BufWriter<File>,
+    include_velocities: bool,
+    include_densities: bool,
+}
+
+impl FrameWriter {
+    pub fn create(
+        path: impl AsRef<Path>,
+        include_velocities: bool,
+        include_densities: bool,
+    ) -> io::Result<Self> {
+        Ok(FrameWriter {
+            file: BufWriter::new(File::create(path)?),
+            include_velocities,
+            include_densities,
+        })
+    }
+
+    /// Writes the current state of `s` as one frame.
+    pub fn write_frame(&mut self, s: &Simulation) -> io::Result<()> {
+        let count = s.positions.len();
+        let mut flags = 0u8;
+        if self.include_velocities {
+            flags |= VELOCITIES_FLAG;
+        }
+        if self.include_densities {
+            flags |= DENSITIES_FLAG;
+        }
+
+        self.file.write_all(&(count as u32).to_le_bytes())?;
+        self.file.write_all(&[flags])?;
+
+        for p in &s.positions {
+            self.file.write_all(&p.x.to_le_bytes())?;
+            self.file.write_all(&p.y.to_le_bytes())?;
+            self.file.write_all(&p.z.to_le_bytes())?;
+        }
+
+        if self.include_velocities {
+            for v in &s.velocities {
+                self.file.write_all(&v.x.to_le_bytes())?;
+                self.file.write_all(&v.y.to_le_bytes())?;
+                self.file.write_all(&v.z.to_le_bytes())?;
+            }
+        }
+
+        if self.include_densities {
+            for d in &s.density {
+                self.file.write_all(&d.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Streams frames back in from a dump file, one at a time, for offline
+/// post-processing or re-rendering.
+pub struct FrameReader {
+    file: BufReader<File>,
+}
+
+impl FrameReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(FrameReader {
+            file: BufReader::new(File::open(path)?),
+        })
+    }
+
+    fn read_f32(&mut self) -> io::Result<Scalar> {
+        let mut buf = [0u8; 4];
+        self.file.read_exact(&mut buf)?;
+        Ok(Scalar::from_le_bytes(buf))
+    }
+}
+
+impl Iterator for FrameReader {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut count_buf = [0u8; 4];
+        match self.file.read_exact(&mut count_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        }
+        let count = u32::from_le_bytes(count_buf) as usize;
+
+        let frame = (|| {
+            let mut flags_buf = [0u8; 1];
+            self.file.read_exact(&mut flags_buf)?;
+            let flags = flags_buf[0];
+
+            let mut positions = Vec::with_capacity(count);
+            for _ in 0..count {
+                positions.push(point3(self.read_f32()?, self.read_f32()?, self.read_f32()?));
+            }
+
+            let velocities = if flags & VELOCITIES_FLAG != 0 {
+                let mut velocities = Vec::with_capacity(count);
+                for _ in 0..count {
+                    velocities.push(vec3(self.read_f32()?, self.read_f32()?, self.read_f32()?));
+                }
+                Some(velocities)
+            } else {
+                None
+            };
+
+            let densities = if flags & DENSITIES_FLAG != 0 {
+                let mut densities = Vec::with_capacity(count);
+                for _ in 0..count {
+                    densities.push(self.read_f32()?);
+                }
+                Some(densities)
+            } else {
+                None
+            };
+
+            Ok(Frame {
+                positions,
+                velocities,
+                densities,
+            })
+        })();
+
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Matrix3;
+    use num::Zero;
+
+    fn sample_simulation() -> Simulation {
+        Simulation {
+            masses: vec![1.0, 2.0, 3.0],
+            positions: vec![point3(0.0, 1.0, 2.0), point3(-1.5, 0.5, 3.25), point3(9.0, -9.0, 0.0)],
+            velocities: vec![vec3(0.1, 0.2, 0.3), vec3(-1.0, 0.0, 1.0), vec3(2.5, 2.5, 2.5)],
+            force: vec![Vec3::zero(); 3],
+            density: vec![100.0, 150.5, 200.25],
+            pressure: vec![0.0, 1.0, 2.0],
+            affine_velocity: vec![Matrix3::zero(); 3],
+        }
+    }
+
+    #[test]
+    fn round_trips_positions_velocities_and_densities() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("sph_solver_frame_test_{}.bin", std::process::id()));
+
+        let s = sample_simulation();
+        let mut writer = FrameWriter::create(&path, true, true).unwrap();
+        writer.write_frame(&s).unwrap();
+        writer.write_frame(&s).unwrap();
+        drop(writer);
+
+        let frames: Vec<Frame> = FrameReader::open(&path)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            assert_eq!(frame.positions, s.positions);
+            assert_eq!(frame.velocities.as_ref().unwrap(), &s.velocities);
+            assert_eq!(frame.densities.as_ref().unwrap(), &s.density);
+        }
+    }
+
+    #[test]
+    fn omits_velocities_and_densities_when_not_requested() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("sph_solver_frame_test_minimal_{}.bin", std::process::id()));
+
+        let s = sample_simulation();
+        let mut writer = FrameWriter::create(&path, false, false).unwrap();
+        writer.write_frame(&s).unwrap();
+        drop(writer);
+
+        let frame = FrameReader::open(&path).unwrap().next().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(frame.positions, s.positions);
+        assert!(frame.velocities.is_none());
+        assert!(frame.densities.is_none());
+    }
+}
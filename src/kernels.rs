@@ -0,0 +1,192 @@
+//! Smoothing kernels used to weight neighbor contributions by distance.
+
+use cgmath::prelude::*;
+
+use crate::{Scalar, Vec3};
+
+/// A radially symmetric smoothing kernel `W(r, h)`, along with its gradient
+/// and Laplacian, used to interpolate per-particle quantities over the
+/// neighborhood of radius `h`.
+pub trait SmoothingKernel {
+    fn value(r: Vec3, h: Scalar) -> Scalar;
+
+    fn gradient_mag(r: Vec3, h: Scalar) -> Scalar;
+
+    /// The full gradient vector, pointing along `r`.
+    fn gradient(r: Vec3, h: Scalar) -> Vec3 {
+        let r_mag = r.magnitude();
+        if r_mag > 0. {
+            Self::gradient_mag(r, h) * r / r_mag
+        } else {
+            Vec3::zero()
+        }
+    }
+
+    fn laplacian(r: Vec3, h: Scalar) -> Scalar;
+}
+
+pub struct SpikyKernel;
+
+impl SmoothingKernel for SpikyKernel {
+    fn value(r: Vec3, h: Scalar) -> Scalar {
+        let r_mag = r.magnitude();
+        if r_mag >= 0. && r_mag <= h {
+            let c = 15. / (std::f32::consts::PI * h.powi(6));
+            let h_sub_r = h - r_mag;
+            c * h_sub_r * h_sub_r * h_sub_r
+        } else {
+            0.
+        }
+    }
+
+    fn gradient_mag(r: Vec3, h: Scalar) -> Scalar {
+        let r_mag = r.magnitude();
+        if r_mag >= 0. && r_mag <= h {
+            let c = 15. * -3. / (std::f32::consts::PI * h.powi(6));
+            let h_sub_r = h - r_mag;
+            c * h_sub_r * h_sub_r
+        } else {
+            0.
+        }
+    }
+
+    fn laplacian(r: Vec3, h: Scalar) -> Scalar {
+        let r_mag = r.magnitude();
+        if r_mag > 0. && r_mag <= h {
+            let c = 15. / (std::f32::consts::PI * h.powi(6));
+            let h_sub_r = h - r_mag;
+            6. * c * h_sub_r - 6. * c * h_sub_r * h_sub_r / r_mag
+        } else {
+            0.
+        }
+    }
+}
+
+pub struct Poly6Kernel;
+
+impl SmoothingKernel for Poly6Kernel {
+    fn value(r: Vec3, h: Scalar) -> Scalar {
+        let c = 315. / (64. * std::f32::consts::PI * h.powi(9));
+        let mag2 = r.magnitude2();
+        if mag2 <= h * h && mag2 > 0. {
+            c * (h * h - mag2).powi(3)
+        } else {
+            0.
+        }
+    }
+
+    fn gradient_mag(r: Vec3, h: Scalar) -> Scalar {
+        let c = 315. / (64. * std::f32::consts::PI * h.powi(9));
+        let mag2 = r.magnitude2();
+        if mag2 <= h * h && mag2 > 0. {
+            c * 3. * -2. * mag2.sqrt() * (h * h - mag2) * (h * h - mag2)
+        } else {
+            0.
+        }
+    }
+
+    fn laplacian(r: Vec3, h: Scalar) -> Scalar {
+        let c = 315. / (64. * std::f32::consts::PI * h.powi(9));
+        let mag2 = r.magnitude2();
+        if mag2 <= h * h && mag2 > 0. {
+            -6. * c * (h * h - mag2) * (3. * h * h - 7. * mag2)
+        } else {
+            0.
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::vec3;
+
+    /// Central-difference gradient/Laplacian of `K::value`, used as a
+    /// reference to check the closed-form derivatives against.
+    fn numerical_gradient_mag<K: SmoothingKernel>(r: Vec3, h: Scalar, eps: Scalar) -> Scalar {
+        let r_mag = r.magnitude();
+        let dir = r / r_mag;
+        let f_plus = K::value(r + dir * eps, h);
+        let f_minus = K::value(r - dir * eps, h);
+        (f_plus - f_minus) / (2. * eps)
+    }
+
+    fn numerical_laplacian<K: SmoothingKernel>(r: Vec3, h: Scalar, eps: Scalar) -> Scalar {
+        let f0 = K::value(r, h);
+        [vec3(1., 0., 0.), vec3(0., 1., 0.), vec3(0., 0., 1.)]
+            .iter()
+            .map(|&axis| (K::value(r + axis * eps, h) - 2. * f0 + K::value(r - axis * eps, h)) / (eps * eps))
+            .sum()
+    }
+
+    #[test]
+    fn poly6_value_vanishes_at_and_beyond_support_radius() {
+        let h = 1.0;
+        assert_eq!(Poly6Kernel::value(vec3(h, 0., 0.), h), 0.);
+        assert_eq!(Poly6Kernel::value(vec3(h * 2., 0., 0.), h), 0.);
+        assert!(Poly6Kernel::value(vec3(0.5, 0., 0.), h) > 0.);
+    }
+
+    #[test]
+    fn poly6_gradient_matches_numerical_derivative() {
+        let h = 1.0;
+        let r = vec3(0.3, 0.2, 0.1);
+        let analytic = Poly6Kernel::gradient_mag(r, h);
+        let numerical = numerical_gradient_mag::<Poly6Kernel>(r, h, 1e-2);
+        assert!(
+            (analytic - numerical).abs() < 5e-2,
+            "analytic={analytic} numerical={numerical}"
+        );
+    }
+
+    #[test]
+    fn poly6_laplacian_matches_numerical_laplacian() {
+        let h = 1.0;
+        let r = vec3(0.3, 0.2, 0.1);
+        let analytic = Poly6Kernel::laplacian(r, h);
+        let numerical = numerical_laplacian::<Poly6Kernel>(r, h, 1e-2);
+        assert!(
+            (analytic - numerical).abs() < 5e-2,
+            "analytic={analytic} numerical={numerical}"
+        );
+    }
+
+    #[test]
+    fn spiky_gradient_matches_numerical_derivative() {
+        let h = 1.0;
+        let r = vec3(0.3, 0.2, 0.1);
+        let analytic = SpikyKernel::gradient_mag(r, h);
+        let numerical = numerical_gradient_mag::<SpikyKernel>(r, h, 1e-2);
+        assert!(
+            (analytic - numerical).abs() < 5e-2,
+            "analytic={analytic} numerical={numerical}"
+        );
+    }
+
+    #[test]
+    fn spiky_laplacian_matches_numerical_laplacian() {
+        let h = 1.0;
+        let r = vec3(0.3, 0.2, 0.1);
+        let analytic = SpikyKernel::laplacian(r, h);
+        let numerical = numerical_laplacian::<SpikyKernel>(r, h, 1e-2);
+        assert!(
+            (analytic - numerical).abs() < 5e-2,
+            "analytic={analytic} numerical={numerical}"
+        );
+    }
+
+    #[test]
+    fn gradient_vector_is_collinear_with_r_and_matches_gradient_mag() {
+        let h = 1.0;
+        let r = vec3(0.3, 0.2, 0.1);
+        let gradient_mag = Poly6Kernel::gradient_mag(r, h);
+        let grad = Poly6Kernel::gradient(r, h);
+
+        // `gradient` is defined as `gradient_mag * r.normalize()`, so it must
+        // be exactly collinear with `r` (same direction if gradient_mag > 0,
+        // opposite if < 0, since the kernel is decreasing in r).
+        assert!((grad.magnitude() - gradient_mag.abs()).abs() < 1e-5);
+        let cross = r.cross(grad);
+        assert!(cross.magnitude() < 1e-5, "grad not collinear with r: {cross:?}");
+    }
+}
@@ -1,12 +1,23 @@
+mod apic;
+mod domain;
+mod emitter;
+mod io;
+mod kernels;
 mod render;
+mod sph;
+mod spatial_hash;
 
 use std::sync::mpsc::channel;
 
-use cgmath::prelude::*;
-use cgmath::{point3, vec3, Point3, Vector3};
+use apic::{ApicGrid, SimulationMode};
+use cgmath::{point3, vec3, Matrix3, Point3, Vector3};
+use domain::Domain;
+use emitter::Emitter;
+use io::FrameWriter;
 use num::Zero;
-use rand::Rng;
 use render::Vertex;
+use sph::SphSettings;
+use spatial_hash::SpatialHash;
 
 trait SPHDiscretization {}
 
@@ -19,101 +30,92 @@ pub struct Simulation {
     pub positions: Vec<Point3<Scalar>>,
     pub velocities: Vec<Vec3>,
     pub force: Vec<Vec3>,
+    pub density: Vec<Scalar>,
+    pub pressure: Vec<Scalar>,
+    pub affine_velocity: Vec<Matrix3<Scalar>>,
 }
 
-trait SmoothingKernel {
-    fn value(r: Vec3, h: Scalar) -> Scalar;
-
-    fn gradient_mag(r: Vec3, h: Scalar) -> Scalar;
-}
-
-struct SpikyKernel;
-
-impl SmoothingKernel for SpikyKernel {
-    fn value(r: Vec3, h: Scalar) -> Scalar {
-        let r_mag = r.magnitude();
-        if r_mag >= 0. && r_mag <= h {
-            let c = 15. / (std::f32::consts::PI * h.powi(6));
-            let h_sub_r = h - r_mag;
-            c * h_sub_r * h_sub_r * h_sub_r
-        } else {
-            0.
-        }
-    }
-
-    fn gradient_mag(r: Vector3<Scalar>, h: Scalar) -> Scalar {
-        let r_mag = r.magnitude();
-        if r_mag >= 0. && r_mag <= h {
-            let c = 15. * -3. / (std::f32::consts::PI * h.powi(6));
-            let h_sub_r = h - r_mag;
-            c * h_sub_r * h_sub_r
-        } else {
-            0.
-        }
-    }
-}
-
-struct Poly6Kernel;
+fn main() {
+    let mut rng = rand::thread_rng();
 
-impl SmoothingKernel for Poly6Kernel {
-    fn value(r: Vector3<Scalar>, h: Scalar) -> Scalar {
-        let c = 315. / (64. * std::f32::consts::PI * h.powi(9));
-        let mag2 = r.magnitude2();
-        if mag2 <= h * h && mag2 > 0. {
-            c * (h * h - mag2).powi(3)
-        } else {
-            0.
-        }
-    }
+    let emitter = Emitter::from_env();
+    let num_particles = emitter.count();
 
-    fn gradient_mag(r: Vec3, h: Scalar) -> Scalar {
-        let c = 315. / (64. * std::f32::consts::PI * h.powi(9));
-        let mag2 = r.magnitude2();
-        if mag2 <= h * h && mag2 > 0. {
-            c * 3. * -2. * mag2.sqrt() * (h * h - mag2) * (h * h - mag2)
-        } else {
-            0.
-        }
-    }
-}
-
-fn main() {
-    let num_particles = 1000;
     let mut s = Simulation {
         masses: Vec::new(),
         positions: Vec::new(),
         velocities: Vec::new(),
         force: Vec::new(),
+        density: vec![0.; num_particles],
+        pressure: vec![0.; num_particles],
+        affine_velocity: vec![Matrix3::zero(); num_particles],
     };
 
-    let mut rng = rand::thread_rng();
+    let settings = SphSettings {
+        rest_density: 150.,
+        stiffness_k: 5.,
+        viscosity: 0.1,
+        h: 1.,
+        timestep: 0.01,
+    };
+
+    let domain = Domain {
+        origin: point3(-2., -2., -2.),
+        size: vec3(4., 4., 4.),
+    };
+    let restitution = 0.5;
+    let mode = SimulationMode::from_env();
+    let gravity = vec3(0., -9.8, 0.);
 
-    let h = 1.;
-    for i in 0..num_particles {
+    s.positions = emitter.seed(&mut rng);
+    for _ in 0..num_particles {
         s.masses.push(1.0);
-        s.positions.push(point3(
-            rng.gen::<Scalar>() * 2. - 1.,
-            rng.gen::<Scalar>() * 2. - 1.,
-            rng.gen::<Scalar>() * 2. - 1.,
-        ));
         s.velocities.push(vec3(0., 0., 0.));
-        s.force.push(-0.1 * (s.positions[i].to_vec()));
+        s.force.push(Vec3::zero());
     }
 
-    let delta_time = 0.01;
     let (tx, rx) = channel::<Vec<Vertex>>();
 
+    let mut frame_writer =
+        FrameWriter::create("frames.bin", true, true).expect("Failed to create frame dump");
+
     std::thread::spawn(move || loop {
         let mut verts = Vec::with_capacity(num_particles);
+
+        let grid = SpatialHash::build(&s.positions, settings.h);
+        sph::compute_densities(&mut s, &grid, settings.h);
+        sph::compute_pressures(&mut s, &settings);
+        sph::accumulate_forces(&mut s, &grid, &settings);
+
         for i in 0..num_particles {
-            s.velocities[i] = s.velocities[i] + delta_time / s.masses[i] * s.force[i];
-            s.positions[i] = s.positions[i] + delta_time * s.velocities[i];
+            s.velocities[i] = s.velocities[i] + settings.timestep / s.masses[i] * s.force[i];
+        }
 
-            let density: Scalar = (0..num_particles)
-                .map(|j| s.masses[j] * Poly6Kernel::value(s.positions[i] - s.positions[j], h))
-                .sum();
+        match mode {
+            SimulationMode::Sph => {
+                for i in 0..num_particles {
+                    s.positions[i] = s.positions[i] + settings.timestep * s.velocities[i];
+                }
+            }
+            SimulationMode::Apic => {
+                let mut apic_grid = ApicGrid::new(&domain, settings.h);
+                apic_grid.transfer_from_particles(&s);
+                apic_grid.apply_acceleration(gravity, settings.timestep);
+                apic_grid.transfer_to_particles(&mut s);
+
+                for i in 0..num_particles {
+                    s.positions[i] = s.positions[i] + settings.timestep * s.velocities[i];
+                }
+            }
+        }
 
+        domain::resolve_collisions(&mut s, &domain, restitution);
+
+        frame_writer.write_frame(&s).expect("Failed to write frame dump");
+
+        for i in 0..num_particles {
             let pos = s.positions[i];
+            let density = s.density[i];
             verts.push(Vertex {
                 position: [pos.x, pos.y, pos.z],
                 color: [density / 150., 1., density / 150.],
@@ -0,0 +1,179 @@
+//! Uniform grid acceleration structure for neighbor queries.
+//!
+//! Particles are bucketed into cells of size `h` (the smoothing radius) and
+//! the buckets are hashed into a flat table, built each step with a
+//! counting/radix sort so there is no per-cell allocation.
+
+use cgmath::prelude::*;
+use cgmath::Point3;
+
+use crate::{ParticleIdx, Scalar};
+
+/// Integer cell coordinates for a point discretized at scale `h`.
+fn cell_coord(p: Point3<Scalar>, h: Scalar) -> (i64, i64, i64) {
+    (
+        (p.x / h).floor() as i64,
+        (p.y / h).floor() as i64,
+        (p.z / h).floor() as i64,
+    )
+}
+
+/// Hashes a cell coordinate into `[0, table_size)`.
+fn cell_hash(cx: i64, cy: i64, cz: i64, table_size: usize) -> usize {
+    let h = (cx.wrapping_mul(73856093)) ^ (cy.wrapping_mul(19349663)) ^ (cz.wrapping_mul(83492791));
+    h.rem_euclid(table_size as i64) as usize
+}
+
+/// A spatial hash grid keyed on the smoothing radius `h`, used to find the
+/// particles within `h` of a query particle in roughly constant time instead
+/// of scanning every particle.
+pub struct SpatialHash {
+    h: Scalar,
+    table_size: usize,
+    /// `cell_start[c]..cell_start[c + 1]` indexes into `sorted_indices` for
+    /// the particles hashed to cell `c`.
+    cell_start: Vec<usize>,
+    sorted_indices: Vec<ParticleIdx>,
+}
+
+impl SpatialHash {
+    /// Rebuilds the grid from scratch for the given particle positions.
+    pub fn build(positions: &[Point3<Scalar>], h: Scalar) -> Self {
+        let table_size = (2 * positions.len()).max(1);
+
+        let cell_of: Vec<usize> = positions
+            .iter()
+            .map(|&p| {
+                let (cx, cy, cz) = cell_coord(p, h);
+                cell_hash(cx, cy, cz, table_size)
+            })
+            .collect();
+
+        let mut cell_start = vec![0usize; table_size + 1];
+        for &c in &cell_of {
+            cell_start[c + 1] += 1;
+        }
+        for c in 0..table_size {
+            cell_start[c + 1] += cell_start[c];
+        }
+
+        let mut cursor = cell_start.clone();
+        let mut sorted_indices = vec![0usize; positions.len()];
+        for (i, &c) in cell_of.iter().enumerate() {
+            sorted_indices[cursor[c]] = i;
+            cursor[c] += 1;
+        }
+
+        SpatialHash {
+            h,
+            table_size,
+            cell_start,
+            sorted_indices,
+        }
+    }
+
+    /// Returns the indices of all particles within `h` of particle `i`,
+    /// searching the 3x3x3 block of cells around it.
+    pub fn neighbors(&self, positions: &[Point3<Scalar>], i: ParticleIdx) -> Vec<ParticleIdx> {
+        let (cx, cy, cz) = cell_coord(positions[i], self.h);
+
+        // Two distinct cells in the 3x3x3 block can collide onto the same
+        // table bucket, so dedupe bucket indices before scanning — otherwise
+        // a collided bucket's particles get visited (and returned) twice.
+        let mut buckets: Vec<usize> = Vec::with_capacity(27);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    buckets.push(cell_hash(cx + dx, cy + dy, cz + dz, self.table_size));
+                }
+            }
+        }
+        buckets.sort_unstable();
+        buckets.dedup();
+
+        let mut neighbors = Vec::new();
+        for cell in buckets {
+            for &j in &self.sorted_indices[self.cell_start[cell]..self.cell_start[cell + 1]] {
+                if (positions[j] - positions[i]).magnitude() <= self.h {
+                    neighbors.push(j);
+                }
+            }
+        }
+
+        neighbors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::point3;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Brute-force O(N^2) neighbor search used as a reference to check the
+    /// grid against.
+    fn brute_force_neighbors(positions: &[Point3<Scalar>], h: Scalar, i: ParticleIdx) -> Vec<ParticleIdx> {
+        let mut neighbors: Vec<ParticleIdx> = (0..positions.len())
+            .filter(|&j| (positions[j] - positions[i]).magnitude() <= h)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    #[test]
+    fn matches_brute_force_on_random_cloud() {
+        let h = 1.;
+        let mut rng = StdRng::seed_from_u64(42);
+        let positions: Vec<Point3<Scalar>> = (0..500)
+            .map(|_| {
+                point3(
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                    rng.gen_range(-2.0..2.0),
+                )
+            })
+            .collect();
+
+        let grid = SpatialHash::build(&positions, h);
+
+        for i in 0..positions.len() {
+            let expected = brute_force_neighbors(&positions, h, i);
+            let mut actual = grid.neighbors(&positions, i);
+            actual.sort_unstable();
+            assert_eq!(actual, expected, "mismatch at particle {i}");
+            // every entry should be distinct: a collided bucket must not be
+            // scanned (and its particles returned) more than once.
+            let mut deduped = actual.clone();
+            deduped.dedup();
+            assert_eq!(actual.len(), deduped.len(), "duplicate neighbor at particle {i}");
+        }
+    }
+
+    #[test]
+    fn reproduces_colliding_bucket_regression() {
+        // table_size = 2 * 1000 = 2000, matching `main`'s particle count.
+        let h = 1.;
+        let table_size = 2000;
+        // Offsets (-1,-1,1) and (-1,1,-1) relative to cell (3,0,0) collide
+        // onto the same bucket for this table size.
+        let (cx, cy, cz) = (3i64, 0i64, 0i64);
+        assert_eq!(
+            cell_hash(cx - 1, cy - 1, cz + 1, table_size),
+            cell_hash(cx - 1, cy + 1, cz - 1, table_size)
+        );
+
+        let positions = vec![
+            point3(3.2, 0.2, 0.2), // query particle, in cell (3, 0, 0)
+            point3(2.9, -0.1, 1.0), // in cell (2, -1, 1)
+            point3(2.9, 1.0, -0.1), // in cell (2, 1, -1)
+        ];
+
+        let grid = SpatialHash::build(&positions, h);
+        let mut neighbors = grid.neighbors(&positions, 0);
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        assert_eq!(neighbors, vec![0, 1, 2]);
+        assert_eq!(grid.neighbors(&positions, 0).len(), 3);
+    }
+}
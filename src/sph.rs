@@ -0,0 +1,152 @@
+//! Weakly-compressible SPH pressure and viscosity forces.
+
+use cgmath::prelude::*;
+
+use crate::kernels::{Poly6Kernel, SmoothingKernel, SpikyKernel};
+use crate::spatial_hash::SpatialHash;
+use crate::{Scalar, Simulation, Vec3};
+
+/// Tunables for the weakly-compressible SPH step.
+pub struct SphSettings {
+    pub rest_density: Scalar,
+    pub stiffness_k: Scalar,
+    pub viscosity: Scalar,
+    pub h: Scalar,
+    pub timestep: Scalar,
+}
+
+/// Recomputes `s.density` for every particle from its neighbors via the
+/// Poly6 kernel.
+pub fn compute_densities(s: &mut Simulation, grid: &SpatialHash, h: Scalar) {
+    for i in 0..s.positions.len() {
+        s.density[i] = grid
+            .neighbors(&s.positions, i)
+            .into_iter()
+            .map(|j| s.masses[j] * Poly6Kernel::value(s.positions[i] - s.positions[j], h))
+            .sum();
+    }
+}
+
+/// Derives pressure from density via the Tait-like state equation
+/// `p = k * (ρ − ρ_0)`.
+pub fn compute_pressures(s: &mut Simulation, settings: &SphSettings) {
+    for i in 0..s.positions.len() {
+        s.pressure[i] = settings.stiffness_k * (s.density[i] - settings.rest_density);
+    }
+}
+
+/// Accumulates the symmetric pressure force and the viscosity force into
+/// `s.force`. Requires `s.density`/`s.pressure` to already be up to date.
+pub fn accumulate_forces(s: &mut Simulation, grid: &SpatialHash, settings: &SphSettings) {
+    for i in 0..s.positions.len() {
+        let mut f_pressure = Vec3::zero();
+        let mut f_visc = Vec3::zero();
+
+        for j in grid.neighbors(&s.positions, i) {
+            if j == i {
+                continue;
+            }
+
+            let r = s.positions[i] - s.positions[j];
+            f_pressure -= s.masses[j] * (s.pressure[i] + s.pressure[j]) / (2. * s.density[j])
+                * SpikyKernel::gradient(r, settings.h);
+            f_visc += settings.viscosity * s.masses[j] * (s.velocities[j] - s.velocities[i]) / s.density[j]
+                * Poly6Kernel::laplacian(r, settings.h);
+        }
+
+        s.force[i] = f_pressure + f_visc;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::point3;
+
+    fn settings(h: Scalar, rest_density: Scalar) -> SphSettings {
+        SphSettings {
+            rest_density,
+            stiffness_k: 5.,
+            viscosity: 0.,
+            h,
+            timestep: 0.01,
+        }
+    }
+
+    fn simulation(positions: Vec<cgmath::Point3<Scalar>>) -> Simulation {
+        let n = positions.len();
+        Simulation {
+            masses: vec![1.; n],
+            positions,
+            velocities: vec![Vec3::zero(); n],
+            force: vec![Vec3::zero(); n],
+            density: vec![0.; n],
+            pressure: vec![0.; n],
+            affine_velocity: vec![cgmath::Matrix3::zero(); n],
+        }
+    }
+
+    #[test]
+    fn pressure_force_is_symmetric_for_a_non_rest_density_pair() {
+        let h = 1.0;
+        let settings = settings(h, 150.);
+        let mut s = simulation(vec![point3(0., 0., 0.), point3(0.3, 0., 0.)]);
+
+        let grid = SpatialHash::build(&s.positions, h);
+        compute_densities(&mut s, &grid, h);
+        compute_pressures(&mut s, &settings);
+        accumulate_forces(&mut s, &grid, &settings);
+
+        // Newton's third law: the pressure force particle 0 exerts on
+        // particle 1 must be the exact opposite of the force particle 1
+        // exerts on particle 0 (no viscosity here, so `s.force` is purely
+        // the pressure term).
+        assert!(
+            (s.force[0] + s.force[1]).magnitude() < 1e-4,
+            "f0={:?} f1={:?}",
+            s.force[0],
+            s.force[1]
+        );
+    }
+
+    #[test]
+    fn net_pressure_force_is_near_zero_for_a_lattice_at_rest_density() {
+        let h = 1.0;
+        let rest_density = 150.;
+        let settings = settings(h, rest_density);
+
+        // A regularly spaced lattice with a mass chosen so the interior
+        // density settles at `rest_density` should produce (near) zero net
+        // pressure force on its interior particles.
+        let spacing = 0.2;
+        let mut positions = Vec::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    positions.push(point3(i as Scalar * spacing, j as Scalar * spacing, k as Scalar * spacing));
+                }
+            }
+        }
+        let mut s = simulation(positions);
+
+        let grid = SpatialHash::build(&s.positions, h);
+        compute_densities(&mut s, &grid, h);
+
+        // Rescale masses so the central particle's density matches
+        // `rest_density` exactly, then recompute with the rescaled masses.
+        let center = 2 * 25 + 2 * 5 + 2;
+        let scale = rest_density / s.density[center];
+        for m in &mut s.masses {
+            *m *= scale;
+        }
+        compute_densities(&mut s, &grid, h);
+        compute_pressures(&mut s, &settings);
+        accumulate_forces(&mut s, &grid, &settings);
+
+        assert!(
+            s.force[center].magnitude() < 1e-2,
+            "force at rest density should vanish, got {:?}",
+            s.force[center]
+        );
+    }
+}